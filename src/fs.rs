@@ -0,0 +1,168 @@
+use std::{
+    collections::HashMap,
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+
+/// A single entry yielded by [`Fs::read_dir`].
+#[derive(Debug, Clone)]
+pub struct DirEntry {
+    pub path: PathBuf,
+    pub is_file: bool,
+}
+
+/// An abstraction over "a filesystem" that `walk_dir` and `create_file_tree` are built
+/// on, so a `Tree<Item>` can be produced from something other than the real OS
+/// filesystem (archive contents, remote listings, or a synthetic fixture in tests).
+#[async_trait]
+pub trait Fs: Send + Sync {
+    /// Lists the immediate children of `path`, which must be a directory.
+    async fn read_dir(&self, path: &Path) -> Result<Vec<DirEntry>>;
+
+    /// Whether `path` is a file (as opposed to a directory).
+    async fn is_file(&self, path: &Path) -> Result<bool>;
+
+    /// Resolves `path` to its canonical, absolute form.
+    async fn canonicalize(&self, path: &Path) -> Result<PathBuf>;
+
+    /// Reads the full contents of the file at `path` as a string.
+    async fn read_to_string(&self, path: &Path) -> Result<String>;
+}
+
+/// An [`Fs`] backed by the real OS filesystem.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RealFs;
+
+#[async_trait]
+impl Fs for RealFs {
+    async fn read_dir(&self, path: &Path) -> Result<Vec<DirEntry>> {
+        let mut entries = Vec::new();
+        let mut read_dir = tokio::fs::read_dir(path).await?;
+
+        while let Some(entry) = read_dir.next_entry().await? {
+            let is_file = entry.file_type().await?.is_file();
+            entries.push(DirEntry {
+                path: entry.path(),
+                is_file,
+            });
+        }
+
+        Ok(entries)
+    }
+
+    async fn is_file(&self, path: &Path) -> Result<bool> {
+        Ok(tokio::fs::metadata(path).await?.is_file())
+    }
+
+    async fn canonicalize(&self, path: &Path) -> Result<PathBuf> {
+        Ok(tokio::fs::canonicalize(path).await?)
+    }
+
+    async fn read_to_string(&self, path: &Path) -> Result<String> {
+        Ok(tokio::fs::read_to_string(path).await?)
+    }
+}
+
+/// A node in a [`FakeFs`]'s directory layout, deserialized from a JSON description
+/// where a file is `null` (no content) or a string (its content), and a directory is
+/// an object mapping child names to entries.
+///
+/// ```json
+/// {
+///   "a": {
+///     "b": { "c": null }
+///   },
+///   ".gitignore": "target/\n",
+///   "f": null
+/// }
+/// ```
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(untagged)]
+pub enum FakeEntry {
+    File(Option<String>),
+    Dir(HashMap<String, FakeEntry>),
+}
+
+/// An in-memory [`Fs`] seeded from a [`FakeEntry`] layout, for tests and tools that
+/// want a `Tree<Item>` without touching a real filesystem.
+pub struct FakeFs {
+    root_path: PathBuf,
+    root: FakeEntry,
+}
+
+impl FakeFs {
+    pub fn new(root_path: impl Into<PathBuf>, root: FakeEntry) -> Self {
+        Self {
+            root_path: root_path.into(),
+            root,
+        }
+    }
+
+    /// Builds a `FakeFs` rooted at `root_path` from a JSON directory layout (see
+    /// [`FakeEntry`]).
+    pub fn from_json(root_path: impl Into<PathBuf>, json: &str) -> Result<Self> {
+        let root = serde_json::from_str(json)?;
+        Ok(Self::new(root_path, root))
+    }
+
+    fn lookup(&self, path: &Path) -> Option<&FakeEntry> {
+        let relative = path.strip_prefix(&self.root_path).ok()?;
+        let mut current = &self.root;
+
+        for component in relative.components() {
+            let Component::Normal(name) = component else {
+                continue;
+            };
+            let name = name.to_str()?;
+
+            match current {
+                FakeEntry::Dir(children) => current = children.get(name)?,
+                FakeEntry::File(_) => return None,
+            }
+        }
+
+        Some(current)
+    }
+}
+
+#[async_trait]
+impl Fs for FakeFs {
+    async fn read_dir(&self, path: &Path) -> Result<Vec<DirEntry>> {
+        match self.lookup(path) {
+            Some(FakeEntry::Dir(children)) => Ok(children
+                .iter()
+                .map(|(name, entry)| DirEntry {
+                    path: path.join(name),
+                    is_file: matches!(entry, FakeEntry::File(_)),
+                })
+                .collect()),
+            Some(FakeEntry::File(_)) => Err(anyhow!("{} is not a directory", path.display())),
+            None => Err(anyhow!("no such path in fake fs: {}", path.display())),
+        }
+    }
+
+    async fn is_file(&self, path: &Path) -> Result<bool> {
+        match self.lookup(path) {
+            Some(FakeEntry::File(_)) => Ok(true),
+            Some(FakeEntry::Dir(_)) => Ok(false),
+            None => Err(anyhow!("no such path in fake fs: {}", path.display())),
+        }
+    }
+
+    async fn canonicalize(&self, path: &Path) -> Result<PathBuf> {
+        match self.lookup(path) {
+            Some(_) => Ok(path.to_path_buf()),
+            None => Err(anyhow!("no such path in fake fs: {}", path.display())),
+        }
+    }
+
+    async fn read_to_string(&self, path: &Path) -> Result<String> {
+        match self.lookup(path) {
+            Some(FakeEntry::File(content)) => Ok(content.clone().unwrap_or_default()),
+            Some(FakeEntry::Dir(_)) => Err(anyhow!("{} is not a file", path.display())),
+            None => Err(anyhow!("no such path in fake fs: {}", path.display())),
+        }
+    }
+}
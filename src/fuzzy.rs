@@ -0,0 +1,173 @@
+use std::{
+    cmp::Ordering,
+    sync::atomic::{AtomicBool, Ordering as AtomicOrdering},
+};
+
+use id_tree::{NodeId, Tree};
+
+use crate::walk::Item;
+
+/// A cheap, lowercased set of the distinct characters making up a path, used to
+/// reject a candidate before running the more expensive subsequence match: if the
+/// query contains a character the candidate doesn't have anywhere, it can't match.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct CharBag(u64);
+
+impl CharBag {
+    pub(crate) fn from_str(s: &str) -> Self {
+        let mut bits = 0u64;
+        for c in s.chars().flat_map(char::to_lowercase) {
+            bits |= 1u64 << Self::bit_index(c);
+        }
+        CharBag(bits)
+    }
+
+    fn bit_index(c: char) -> u32 {
+        match c {
+            'a'..='z' => c as u32 - 'a' as u32,
+            '0'..='9' => 26 + (c as u32 - '0' as u32),
+            _ => 36,
+        }
+    }
+
+    /// Whether every character in `other` is also present in `self`.
+    fn is_superset(&self, other: &CharBag) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+/// A single scored match of a query against a path in the tree.
+#[derive(Debug, Clone)]
+pub struct PathMatch {
+    pub node_id: NodeId,
+    pub score: f32,
+    /// Indices into the path's characters that were matched against the query, for highlighting.
+    pub matched_indices: Vec<usize>,
+}
+
+/// Options controlling a fuzzy path search.
+pub struct FuzzyMatchOptions<'a> {
+    pub limit: usize,
+    pub cancelled: &'a AtomicBool,
+}
+
+impl Default for FuzzyMatchOptions<'_> {
+    fn default() -> Self {
+        static NEVER_CANCELLED: AtomicBool = AtomicBool::new(false);
+        Self {
+            limit: 100,
+            cancelled: &NEVER_CANCELLED,
+        }
+    }
+}
+
+/// Scores and ranks every path in `tree` against `query`, returning the best matches
+/// sorted by descending score. Checks `options.cancelled` periodically so a caller can
+/// abort a search over a large tree.
+pub fn fuzzy_match_tree(
+    tree: &Tree<Item>,
+    query: &str,
+    options: FuzzyMatchOptions,
+) -> Vec<PathMatch> {
+    let Some(root_id) = tree.root_node_id() else {
+        return Vec::new();
+    };
+
+    let query_bag = CharBag::from_str(query);
+    let mut matches = Vec::new();
+
+    for (i, node_id) in tree.traverse_pre_order_ids(root_id).unwrap().enumerate() {
+        if i % 256 == 0 && options.cancelled.load(AtomicOrdering::Relaxed) {
+            break;
+        }
+
+        let node = tree.get(&node_id).expect("node id from this tree");
+        let item = node.data();
+
+        if !item.char_bag().is_superset(&query_bag) {
+            continue;
+        }
+
+        let Some(path_str) = item.path().to_str() else {
+            continue;
+        };
+
+        if let Some((score, matched_indices)) = score_match(query, path_str) {
+            matches.push(PathMatch {
+                node_id,
+                score,
+                matched_indices,
+            });
+        }
+    }
+
+    matches.sort_unstable_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+    matches.truncate(options.limit);
+    matches
+}
+
+/// Whether the character at `index` begins a new "word" in `path` - the start of the
+/// string, right after a path separator or `_`/`-`, or a lower-to-upper camelCase
+/// transition - which earns matches there a boundary bonus.
+fn is_boundary(path: &[char], index: usize) -> bool {
+    if index == 0 {
+        return true;
+    }
+
+    let previous = path[index - 1];
+    let current = path[index];
+    matches!(previous, '/' | '_' | '-') || (previous.is_lowercase() && current.is_uppercase())
+}
+
+/// Whether `a` and `b` are the same character, ignoring case.
+fn chars_eq_ignore_case(a: char, b: char) -> bool {
+    a == b || a.to_lowercase().eq(b.to_lowercase())
+}
+
+/// Greedily matches `query` as a subsequence of `path`, scoring boundary matches higher
+/// and penalizing gaps between consecutive matched characters. Returns `None` if `query`
+/// isn't a subsequence of `path` at all. The returned score is in `[0, 1]`.
+fn score_match(query: &str, path: &str) -> Option<(f32, Vec<usize>)> {
+    let query_chars: Vec<char> = query.chars().collect();
+    if query_chars.is_empty() {
+        return Some((1.0, Vec::new()));
+    }
+
+    // A single indexed sequence, so `matched_indices` and `is_boundary` always refer
+    // to the same character `path_chars[i]` the caller will highlight.
+    let path_chars: Vec<char> = path.chars().collect();
+
+    let mut matched_indices = Vec::with_capacity(query_chars.len());
+    let mut query_index = 0;
+    let mut last_match: Option<usize> = None;
+    let mut score = 0.0f32;
+
+    for (i, &c) in path_chars.iter().enumerate() {
+        if query_index >= query_chars.len() {
+            break;
+        }
+        if !chars_eq_ignore_case(c, query_chars[query_index]) {
+            continue;
+        }
+
+        let mut char_score = if is_boundary(&path_chars, i) { 1.0 } else { 0.75 };
+        if let Some(last) = last_match {
+            let gap = i - last - 1;
+            if gap > 0 {
+                let penalty = (0.6 - gap as f32 * 0.05).max(0.2);
+                char_score -= penalty;
+            }
+        }
+
+        score += char_score.max(0.0);
+        matched_indices.push(i);
+        last_match = Some(i);
+        query_index += 1;
+    }
+
+    if query_index < query_chars.len() {
+        return None;
+    }
+
+    Some(((score / query_chars.len() as f32).clamp(0.0, 1.0), matched_indices))
+}
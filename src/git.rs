@@ -0,0 +1,87 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use git2::{ObjectType, Oid, Repository, TreeWalkMode, TreeWalkResult};
+use id_tree::{InsertBehavior, Node, NodeId, Tree};
+
+use crate::walk::Item;
+
+/// The git-specific metadata carried by an [`Item`] built from a repository snapshot:
+/// the entry's raw filemode (e.g. `0o100755` for an executable, `0o120000` for a
+/// symlink) and the id of the blob or tree it points to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GitMetadata {
+    pub mode: i32,
+    pub id: Oid,
+}
+
+/// Builds a `Tree<Item>` from the files and directories at `refname` (a branch name,
+/// commit hash, or `HEAD`) in the git repository at `repo_path`, without checking
+/// anything out to disk. Each resulting `Item` carries its [`GitMetadata`].
+pub fn create_file_tree_from_git(repo_path: &Path, refname: &str) -> Result<Tree<Item>> {
+    let repo = Repository::open(repo_path)?;
+    let commit = repo.revparse_single(refname)?.peel_to_commit()?;
+    let root = commit.tree()?;
+
+    let mut tree = Tree::new();
+    let root_id = tree.insert(
+        Node::new(Item::new(repo_path.to_path_buf(), false)),
+        InsertBehavior::AsRoot,
+    )?;
+
+    // A map from the accumulated, "/"-joined path as it appears in the git tree (""
+    // for the root) to the node id of the directory it names, populated as `walk`
+    // descends in pre-order so a parent is always inserted before its children.
+    let mut node_ids: HashMap<String, NodeId> = HashMap::new();
+    node_ids.insert(String::new(), root_id);
+
+    let mut error = None;
+    root.walk(TreeWalkMode::PreOrder, |parent, entry| {
+        let parent_key = parent.trim_end_matches('/');
+        let Some(&parent_id) = node_ids.get(parent_key) else {
+            return TreeWalkResult::Skip;
+        };
+
+        let Some(name) = entry.name() else {
+            return TreeWalkResult::Skip;
+        };
+
+        let entry_key = if parent_key.is_empty() {
+            name.to_string()
+        } else {
+            format!("{parent_key}/{name}")
+        };
+
+        let is_file = entry.kind() != Some(ObjectType::Tree);
+        let item = Item::with_git(
+            repo_path.join(&entry_key),
+            is_file,
+            GitMetadata {
+                mode: entry.filemode(),
+                id: entry.id(),
+            },
+        );
+
+        match tree.insert(Node::new(item), InsertBehavior::UnderNode(&parent_id)) {
+            Ok(node_id) => {
+                if !is_file {
+                    node_ids.insert(entry_key, node_id);
+                }
+                TreeWalkResult::Ok
+            }
+            Err(err) => {
+                error = Some(err.into());
+                TreeWalkResult::Abort
+            }
+        }
+    })?;
+
+    if let Some(err) = error {
+        return Err(err);
+    }
+
+    Ok(tree)
+}
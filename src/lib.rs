@@ -0,0 +1,5 @@
+pub mod fs;
+pub mod fuzzy;
+pub mod git;
+pub mod walk;
+pub mod watch;
@@ -1,89 +1,206 @@
 use std::{
     collections::HashMap,
-    ffi::OsStr,
     path::{Path, PathBuf},
+    sync::Arc,
 };
 
 use anyhow::Result;
-use async_walkdir::WalkDir;
-use futures::StreamExt;
 use id_tree::{InsertBehavior, Node, NodeId, Tree, TreeBuilder};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+
+use crate::fs::Fs;
+use crate::fuzzy::CharBag;
+use crate::git::GitMetadata;
 
 #[derive(Debug, Clone)]
 pub struct Item {
     path: PathBuf,
     is_file: bool,
+    char_bag: CharBag,
+    git: Option<GitMetadata>,
 }
 
-// TODO: Respect git ignore
-async fn walk_dir(path: &Path, _use_git_ignore: bool) -> Result<Tree<Item>> {
-    let mut walker = WalkDir::new(path);
-    let mut items = Vec::new();
+impl Item {
+    pub(crate) fn new(path: PathBuf, is_file: bool) -> Self {
+        Self::with_git_opt(path, is_file, None)
+    }
+
+    pub(crate) fn with_git(path: PathBuf, is_file: bool, git: GitMetadata) -> Self {
+        Self::with_git_opt(path, is_file, Some(git))
+    }
 
-    while let Some(entry) = walker.next().await {
-        let entry = entry?;
-        let is_file = entry.metadata().await?.is_file();
-        items.push(Item {
-            path: entry.path(),
+    fn with_git_opt(path: PathBuf, is_file: bool, git: Option<GitMetadata>) -> Self {
+        // Built over the same string `fuzzy::score_match` scores against (the full
+        // path) so the bag never rejects a candidate the scorer would have matched.
+        let char_bag = CharBag::from_str(&path.to_string_lossy());
+        Self {
+            path,
             is_file,
-        });
-    }
-
-    // Sorts by the number of parent directories so we always have a node for that parent directory
-    // in the tree before we try to create the node.
-    items.sort_unstable_by_key(|item| item.path.iter().count());
-
-    // A map of path names to node ids to reconstruct the tree from the flat list of items.
-    let mut node_id_map: HashMap<String, NodeId> = HashMap::new();
-    // Converts a path to the a stringified version without a trialing slash.
-    let path_key = |path: &Path| {
-        path.iter()
-            .filter_map(OsStr::to_str)
-            .map(String::from)
-            .collect::<Vec<_>>()
-            .join("/")
+            char_bag,
+            git,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn is_file(&self) -> bool {
+        self.is_file
+    }
+
+    /// The entry's git filemode and object id, if this `Item` was built from a git
+    /// tree rather than a real or virtual filesystem.
+    pub fn git(&self) -> Option<&GitMetadata> {
+        self.git.as_ref()
+    }
+
+    pub(crate) fn char_bag(&self) -> CharBag {
+        self.char_bag
+    }
+}
+
+/// Adds `path`'s gitignore-syntax lines to `builder`, read through `fs` so a virtual
+/// filesystem's rules are honored instead of whatever happens to be on the real disk
+/// at that path. Returns whether anything was there to read.
+async fn add_ignore_file(fs: &dyn Fs, builder: &mut GitignoreBuilder, path: &Path) -> bool {
+    let Ok(content) = fs.read_to_string(path).await else {
+        return false;
     };
 
+    for line in content.lines() {
+        let _ = builder.add_line(None, line);
+    }
+
+    true
+}
+
+/// Builds the matchers that apply at the repository root: its own `.gitignore`
+/// (if present) and `.git/info/exclude`.
+async fn root_ignore_stack(fs: &dyn Fs, root: &Path) -> Vec<Arc<Gitignore>> {
+    let mut builder = GitignoreBuilder::new(root);
+
+    add_ignore_file(fs, &mut builder, &root.join(".gitignore")).await;
+    add_ignore_file(fs, &mut builder, &root.join(".git").join("info").join("exclude")).await;
+
+    match builder.build() {
+        Ok(gitignore) => vec![Arc::new(gitignore)],
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Builds the matcher contributed by `dir`'s own `.gitignore`, if it has one.
+async fn dir_ignore(fs: &dyn Fs, dir: &Path) -> Option<Gitignore> {
+    let mut builder = GitignoreBuilder::new(dir);
+    if !add_ignore_file(fs, &mut builder, &dir.join(".gitignore")).await {
+        return None;
+    }
+
+    builder.build().ok()
+}
+
+/// Checks `path` against a stack of matchers ordered from the repository root down to
+/// the most specific directory. A deeper matcher's explicit decision (ignore or
+/// whitelist) overrides a shallower one, the same precedence git itself applies.
+fn is_ignored(stack: &[Arc<Gitignore>], path: &Path, is_dir: bool) -> bool {
+    let mut ignored = false;
+
+    for matcher in stack {
+        match matcher.matched_path_or_any_parents(path, is_dir) {
+            ignore::Match::None => {}
+            ignore::Match::Ignore(_) => ignored = true,
+            ignore::Match::Whitelist(_) => ignored = false,
+        }
+    }
+
+    ignored
+}
+
+/// The key siblings are ordered by, shared between an eager [`walk_dir`]/[`list_children`]
+/// build and [`crate::watch::Watcher`]'s incremental re-sorts, so a watched insert lands
+/// a row where a rebuild would have put it.
+pub(crate) fn sibling_order_key(path: &Path) -> &Path {
+    path
+}
+
+/// Lists the immediate children of the directory at `node_id`, sorted by path once.
+/// Doesn't touch `tree` itself - it's left to the caller to insert whichever of the
+/// results it wants, which is what lets a UI expand one folder at a time on demand
+/// instead of paying for a walk of the whole tree up front.
+pub async fn list_children(fs: &dyn Fs, tree: &Tree<Item>, node_id: &NodeId) -> Result<Vec<Item>> {
+    let dir = tree.get(node_id)?.data().path();
+    let mut entries = fs.read_dir(dir).await?;
+
+    // The .git directory is never part of the tree, ignore file or not.
+    entries.retain(|entry| !entry.path.components().any(|c| c.as_os_str() == ".git"));
+    entries.sort_unstable_by(|a, b| sibling_order_key(&a.path).cmp(sibling_order_key(&b.path)));
+
+    Ok(entries
+        .into_iter()
+        .map(|entry| Item::new(entry.path, entry.is_file))
+        .collect())
+}
+
+async fn walk_dir(fs: &dyn Fs, path: &Path, use_git_ignore: bool) -> Result<Tree<Item>> {
     let mut tree = Tree::new();
     let root_id = tree.insert(
-        Node::new(Item {
-            path: path.into(),
-            is_file: false,
-        }),
+        Node::new(Item::new(path.into(), false)),
         InsertBehavior::AsRoot,
     )?;
-    node_id_map.insert(path_key(path), root_id);
 
-    for item in items {
-        let parent = item.path.parent().expect("item doesn't have parent path");
-        let parent_id = node_id_map
-            .get(&path_key(parent))
-            .expect("parent not in node id map");
+    // A map of directory node ids to the stack of gitignore matchers that apply to
+    // their children, accumulated from the repository root downward.
+    let mut ignore_stacks: HashMap<NodeId, Vec<Arc<Gitignore>>> = HashMap::new();
+    if use_git_ignore {
+        ignore_stacks.insert(root_id.clone(), root_ignore_stack(fs, path).await);
+    }
+
+    let mut pending_dirs = vec![root_id];
+
+    while let Some(dir_id) = pending_dirs.pop() {
+        let parent_stack = ignore_stacks.get(&dir_id).cloned();
 
-        let node_id_key = path_key(&item.path);
-        let node_id = tree.insert(Node::new(item), InsertBehavior::UnderNode(&parent_id))?;
+        for item in list_children(fs, &tree, &dir_id).await? {
+            let is_dir = !item.is_file();
 
-        // TODO: Figure out a way to do this more elegantly
-        tree.sort_children_by_key(parent_id, |node| path_key(&node.data().path))?;
+            if let Some(parent_stack) = &parent_stack {
+                if is_ignored(parent_stack, item.path(), is_dir) {
+                    continue;
+                }
+            }
 
-        node_id_map.insert(node_id_key, node_id);
+            let own_ignore = if use_git_ignore && is_dir {
+                dir_ignore(fs, item.path()).await
+            } else {
+                None
+            };
+            let child_id = tree.insert(Node::new(item), InsertBehavior::UnderNode(&dir_id))?;
+
+            if is_dir {
+                pending_dirs.push(child_id.clone());
+
+                if use_git_ignore {
+                    let mut stack = parent_stack.clone().unwrap_or_default();
+                    if let Some(own) = own_ignore {
+                        stack.push(Arc::new(own));
+                    }
+                    ignore_stacks.insert(child_id, stack);
+                }
+            }
+        }
     }
 
-    return Ok(tree);
+    Ok(tree)
 }
 
-// TODO: Respect git ignore
 #[allow(dead_code)]
-pub async fn create_file_tree(path: &Path, use_git_ignore: bool) -> Result<Tree<Item>> {
-    Ok(if path.is_file() {
+pub async fn create_file_tree(fs: &dyn Fs, path: &Path, use_git_ignore: bool) -> Result<Tree<Item>> {
+    Ok(if fs.is_file(path).await? {
         TreeBuilder::new()
-            .with_root(Node::new(Item {
-                path: path.into(),
-                is_file: true,
-            }))
+            .with_root(Node::new(Item::new(path.into(), true)))
             .build()
     } else {
-        walk_dir(path, use_git_ignore).await?
+        walk_dir(fs, path, use_git_ignore).await?
     })
 }
 
@@ -91,11 +208,12 @@ pub async fn create_file_tree(path: &Path, use_git_ignore: bool) -> Result<Tree<
 mod tests {
 
     use super::*;
+    use crate::fs::RealFs;
     use std::path::Path;
 
     #[tokio::test]
     async fn tree_from_dirs() {
-        let tree = create_file_tree(Path::new("testdata"), false)
+        let tree = create_file_tree(&RealFs, Path::new("testdata"), false)
             .await
             .unwrap();
         let mut paths = Vec::new();
@@ -124,7 +242,7 @@ mod tests {
 
     #[tokio::test]
     async fn tree_from_file() {
-        let tree = create_file_tree(Path::new("testdata/a/f"), false)
+        let tree = create_file_tree(&RealFs, Path::new("testdata/a/f"), false)
             .await
             .unwrap();
         let mut paths = Vec::new();
@@ -137,4 +255,93 @@ mod tests {
         assert_eq!(paths.len(), 1);
         assert_eq!(Path::new("testdata/a/f"), paths[0]);
     }
+
+    #[tokio::test]
+    async fn tree_respects_gitignore() {
+        use crate::fs::FakeFs;
+
+        let fs = FakeFs::from_json(
+            "testdata/ignored",
+            r#"{
+                ".gitignore": "target/\n",
+                "src": { "main.rs": null },
+                "target": { "debug": null }
+            }"#,
+        )
+        .unwrap();
+
+        let tree = create_file_tree(&fs, Path::new("testdata/ignored"), true)
+            .await
+            .unwrap();
+        let mut paths = Vec::new();
+        let root_id = tree.root_node_id().unwrap();
+
+        for node in tree.traverse_pre_order(root_id).unwrap() {
+            paths.push(node.data().path.as_path());
+        }
+
+        assert!(!paths.contains(&Path::new("testdata/ignored/target")));
+        assert!(paths.contains(&Path::new("testdata/ignored/src")));
+    }
+
+    #[tokio::test]
+    async fn tree_from_fake_fs() {
+        use crate::fs::FakeFs;
+
+        let fs = FakeFs::from_json(
+            "testdata",
+            r#"{
+                "a": {
+                    "b": { "c": { ".gitkeep": null }, "d": { ".gitkeep": null } },
+                    "e": { ".gitkeep": null },
+                    "f": null
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let tree = create_file_tree(&fs, Path::new("testdata"), false)
+            .await
+            .unwrap();
+        let mut paths = Vec::new();
+        let root_id = tree.root_node_id().unwrap();
+
+        for node in tree.traverse_pre_order(root_id).unwrap() {
+            paths.push(node.data().path.as_path());
+        }
+
+        assert_eq!(
+            &[
+                Path::new("testdata/"),
+                Path::new("testdata/a"),
+                Path::new("testdata/a/b"),
+                Path::new("testdata/a/b/c"),
+                Path::new("testdata/a/b/c/.gitkeep"),
+                Path::new("testdata/a/b/d"),
+                Path::new("testdata/a/b/d/.gitkeep"),
+                Path::new("testdata/a/e"),
+                Path::new("testdata/a/e/.gitkeep"),
+                Path::new("testdata/a/f")
+            ],
+            paths.as_slice()
+        )
+    }
+
+    #[tokio::test]
+    async fn list_children_expands_one_level() {
+        let mut tree = Tree::new();
+        let root_id = tree
+            .insert(
+                Node::new(Item::new(PathBuf::from("testdata"), false)),
+                InsertBehavior::AsRoot,
+            )
+            .unwrap();
+
+        let children = list_children(&RealFs, &tree, &root_id).await.unwrap();
+
+        assert_eq!(
+            children.iter().map(|item| item.path()).collect::<Vec<_>>(),
+            &[Path::new("testdata/a")]
+        );
+    }
 }
@@ -0,0 +1,229 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use id_tree::{InsertBehavior, Node, NodeId, RemoveBehavior, Tree};
+use notify::{
+    event::{ModifyKind, RenameMode},
+    Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher as _,
+};
+use tokio::sync::mpsc;
+
+use crate::walk::{sibling_order_key, Item};
+
+/// A mutation applied to a watched `Tree<Item>` in response to a filesystem event. The
+/// tree has already been updated by the time this is reported - look an `Added` or
+/// `Moved` id up in [`Watcher::tree`] to refresh just the affected row. A `Removed` id
+/// is no longer in the tree.
+#[derive(Debug, Clone)]
+pub enum TreeChange {
+    Added(NodeId),
+    Removed(NodeId),
+    Moved(NodeId),
+}
+
+/// Converts a path to the slash-joined, trailing-slash-free key the watcher's
+/// path -> node id index is keyed by.
+fn path_key(path: &Path) -> String {
+    path.iter()
+        .filter_map(|c| c.to_str())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Lists the immediate children of `path` already on disk. `insert` recurses into
+/// each directory child itself, so this must stay single-level - walking the whole
+/// subtree here too would have every nested entry inserted twice.
+fn list_children(path: &Path) -> Vec<PathBuf> {
+    let Ok(read_dir) = std::fs::read_dir(path) else {
+        return Vec::new();
+    };
+
+    read_dir
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|entry_path| !entry_path.components().any(|c| c.as_os_str() == ".git"))
+        .collect()
+}
+
+/// Keeps a `Tree<Item>` (typically built by [`crate::walk::create_file_tree`]) in sync
+/// with the directory it was scanned from, translating `notify` filesystem events into
+/// tree mutations instead of requiring a full rebuild.
+pub struct Watcher {
+    tree: Tree<Item>,
+    // A persistent path -> node id index, kept up to date as events are applied.
+    node_ids: HashMap<String, NodeId>,
+    // `Name(From)` halves of a rename waiting for their `Name(To)` counterpart, keyed
+    // by the tracker cookie the two events share. Raw inotify (what `recommended_watcher`
+    // uses on Linux, with no debouncer in front of it) never coalesces a rename into a
+    // single `Name(Both)` event - it always delivers `From` and `To` separately.
+    pending_renames: HashMap<usize, PathBuf>,
+    events: mpsc::UnboundedReceiver<Event>,
+    _watcher: RecommendedWatcher,
+}
+
+impl Watcher {
+    /// Starts watching the root of `tree` for changes.
+    pub fn new(tree: Tree<Item>) -> Result<Self> {
+        let root_id = tree
+            .root_node_id()
+            .ok_or_else(|| anyhow!("cannot watch a tree without a root"))?
+            .clone();
+        let root_path = tree.get(&root_id)?.data().path().to_path_buf();
+
+        let mut node_ids = HashMap::new();
+        for node_id in tree.traverse_pre_order_ids(&root_id)? {
+            let path = tree.get(&node_id)?.data().path().to_path_buf();
+            node_ids.insert(path_key(&path), node_id);
+        }
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            if let Ok(event) = event {
+                let _ = tx.send(event);
+            }
+        })?;
+        watcher.watch(&root_path, RecursiveMode::Recursive)?;
+
+        Ok(Self {
+            tree,
+            node_ids,
+            pending_renames: HashMap::new(),
+            events: rx,
+            _watcher: watcher,
+        })
+    }
+
+    /// The tree as of the last applied change.
+    pub fn tree(&self) -> &Tree<Item> {
+        &self.tree
+    }
+
+    /// Waits for the next filesystem event and applies it to the tree, returning the
+    /// resulting change. Events that don't map to a tracked path (e.g. a rename whose
+    /// destination is outside the watched root) are applied silently and skipped.
+    /// Returns `None` once the watcher's event source is gone.
+    pub async fn next_change(&mut self) -> Option<Result<TreeChange>> {
+        loop {
+            let event = self.events.recv().await?;
+            match self.apply(event) {
+                Ok(Some(change)) => return Some(Ok(change)),
+                Ok(None) => continue,
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+
+    fn apply(&mut self, event: Event) -> Result<Option<TreeChange>> {
+        match event.kind {
+            EventKind::Create(_) => {
+                let Some(path) = event.paths.into_iter().next() else {
+                    return Ok(None);
+                };
+                Ok(self.insert(&path)?.map(TreeChange::Added))
+            }
+            EventKind::Remove(_) => {
+                let Some(path) = event.paths.into_iter().next() else {
+                    return Ok(None);
+                };
+                Ok(self.remove(&path).map(TreeChange::Removed))
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => {
+                let [from, to]: [PathBuf; 2] = event
+                    .paths
+                    .try_into()
+                    .map_err(|_| anyhow!("rename event did not carry both paths"))?;
+                Ok(self.rename(&from, to)?.map(TreeChange::Moved))
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+                let Some(from) = event.paths.into_iter().next() else {
+                    return Ok(None);
+                };
+                match event.attrs.tracker() {
+                    Some(cookie) => {
+                        self.pending_renames.insert(cookie, from);
+                        Ok(None)
+                    }
+                    // No cookie to pair this with its `To` half - treat it as a plain
+                    // removal rather than silently dropping it.
+                    None => Ok(self.remove(&from).map(TreeChange::Removed)),
+                }
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+                let Some(to) = event.paths.into_iter().next() else {
+                    return Ok(None);
+                };
+                match event.attrs.tracker().and_then(|cookie| self.pending_renames.remove(&cookie))
+                {
+                    Some(from) => Ok(self.rename(&from, to)?.map(TreeChange::Moved)),
+                    // No pending `From` half - e.g. the source was outside the watched
+                    // root - so this is effectively a fresh insert.
+                    None => Ok(self.insert(&to)?.map(TreeChange::Added)),
+                }
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Inserts `path` - and, if it's a directory, everything already underneath it on
+    /// disk - under its parent, re-sorting only that one sibling group.
+    fn insert(&mut self, path: &Path) -> Result<Option<NodeId>> {
+        // Already tracked - e.g. `insert`'s own recursion into a newly-created
+        // directory beat notify's Create event for this same child here, or the
+        // reverse. Either way there's nothing left to do.
+        if let Some(node_id) = self.node_ids.get(&path_key(path)) {
+            return Ok(Some(node_id.clone()));
+        }
+
+        let Some(parent) = path.parent() else {
+            return Ok(None);
+        };
+        let Some(parent_id) = self.node_ids.get(&path_key(parent)).cloned() else {
+            // The parent isn't tracked - outside the watched tree, or already pruned -
+            // so there's nothing to attach this entry to.
+            return Ok(None);
+        };
+
+        let is_file = path.is_file();
+        let node_id = self.tree.insert(
+            Node::new(Item::new(path.to_path_buf(), is_file)),
+            InsertBehavior::UnderNode(&parent_id),
+        )?;
+        self.tree.sort_children_by_key(&parent_id, |node| {
+            sibling_order_key(node.data().path()).to_path_buf()
+        })?;
+        self.node_ids.insert(path_key(path), node_id.clone());
+
+        if !is_file {
+            for child in list_children(path) {
+                self.insert(&child)?;
+            }
+        }
+
+        Ok(Some(node_id))
+    }
+
+    /// Drops the subtree rooted at `path`, including its entries in the path index.
+    fn remove(&mut self, path: &Path) -> Option<NodeId> {
+        let key = path_key(path);
+        let node_id = self.node_ids.remove(&key)?;
+
+        let prefix = format!("{key}/");
+        self.node_ids.retain(|other, _| !other.starts_with(&prefix));
+        let _ = self.tree.remove_node(node_id.clone(), RemoveBehavior::DropChildren);
+
+        Some(node_id)
+    }
+
+    /// Moves the subtree at `from` so it appears under `to`'s parent instead. In
+    /// practice this means dropping the old subtree and re-inserting it - and whatever
+    /// is now on disk beneath it - at its new location. `from` not being tracked (e.g.
+    /// it was moved in from outside the watched root) just means there's nothing to
+    /// drop - `to` still needs to be inserted.
+    fn rename(&mut self, from: &Path, to: PathBuf) -> Result<Option<NodeId>> {
+        self.remove(from);
+        self.insert(&to)
+    }
+}